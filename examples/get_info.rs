@@ -22,14 +22,11 @@ fn main() -> Result<()> {
     })
     .expect("failed to configure SPI device");
 
-    let mut tmc = Tmc4671::new(spi);
+    let mut tmc = Tmc4671::with_enable(spi, eni);
 
     loop {
-        eni.set_value(1)?;
         let si_type = tmc.get_chip_info(CHIP_INFO_ADDRESS::SI_TYPE)?;
 
-        eni.set_value(0)?;
-
         let type_bytes = si_type.to_be_bytes();
         let si_type_str = String::from_utf8_lossy(&type_bytes);
 
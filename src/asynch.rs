@@ -0,0 +1,74 @@
+//! Async mirror of [`crate::Tmc4671`] built on [`embedded_hal_async::spi::SpiDevice`].
+//!
+//! Enabled via the `async` feature, for executors (e.g. Embassy) where the SPI
+//! peripheral driver cooperatively yields during a transfer. [`Datagram`](spi::Datagram)
+//! encoding/parsing and [`Tmc4671Error`] are shared with the blocking driver so both
+//! paths stay in sync.
+use crate::spi::{self, Datagram};
+use crate::Tmc4671Error;
+use embedded_hal::spi::Error;
+use embedded_hal_async::spi::SpiDevice;
+use nom::Finish;
+
+pub struct Tmc4671Async<SPI: SpiDevice> {
+    spi_device: SPI,
+}
+
+impl<SPI: SpiDevice> Tmc4671Async<SPI> {
+    pub fn new(spi_device: SPI) -> Self {
+        Tmc4671Async { spi_device }
+    }
+}
+
+impl<SPI: SpiDevice> Tmc4671Async<SPI> {
+    pub async fn get_chip_info(
+        &mut self,
+        info: spi::constants::CHIP_INFO_ADDRESS,
+    ) -> Result<u32, Tmc4671Error> {
+        self.write_register(spi::registers::CHIPINFO_ADDR, info as u32)
+            .await?;
+        self.read_register(spi::registers::CHIPINFO_DATA).await
+    }
+}
+
+impl<SPI: SpiDevice> Tmc4671Async<SPI> {
+    pub async fn read_register(&mut self, register: u8) -> Result<u32, Tmc4671Error> {
+        let datagram = Datagram {
+            write_not_read: false,
+            address: register,
+            data: 0x00_00_00_00,
+        };
+
+        let received_datagram = self.transfer_datagram(datagram).await?;
+
+        Ok(received_datagram.data)
+    }
+
+    async fn transfer_datagram(&mut self, datagram: Datagram) -> Result<Datagram, Tmc4671Error> {
+        let mut buffer = datagram.bytes();
+
+        self.spi_device
+            .transfer_in_place(&mut buffer)
+            .await
+            .map_err(|err| Tmc4671Error::CommunicationError(err.kind()))?;
+
+        let (_, received_datagram) = Datagram::parse(&buffer)
+            .finish()
+            .map_err(|_err| Tmc4671Error::ParseError)?;
+
+        // debug_assert_eq!(datagram.address, received_datagram.address);
+        Ok(received_datagram)
+    }
+
+    pub async fn write_register(&mut self, register: u8, data: u32) -> Result<(), Tmc4671Error> {
+        let datagram = Datagram {
+            write_not_read: true,
+            address: register,
+            data,
+        };
+
+        let _received_datagram = self.transfer_datagram(datagram).await?;
+
+        Ok(())
+    }
+}
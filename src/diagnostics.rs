@@ -0,0 +1,184 @@
+//! Status-flag and fault diagnostics.
+//!
+//! Decodes `STATUS_FLAGS` into a [`StatusFlags`] bitflags value and exposes the
+//! `PID_ERROR_ADDR`/`PID_ERROR_DATA` indirect-access pair, so applications can trip a
+//! safe-state shutdown on fault conditions instead of reading the datasheet bit-by-bit.
+//!
+//! Bit layout per the TMC4671-LA datasheet.
+//! ©2022 TRINAMIC Motion Control GmbH & Co. KG, Hamburg, Germany
+use crate::enable::EnableLine;
+use crate::spi::registers;
+use crate::{Tmc4671, Tmc4671Error};
+use bitflags::bitflags;
+use embedded_hal::spi::SpiDevice;
+
+bitflags! {
+    /// Decoded `STATUS_FLAGS` register.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct StatusFlags: u32 {
+        const PID_X_TARGET_LIMIT = 1 << 0;
+        const PID_X_ERROR_SUM_LIMIT = 1 << 1;
+        const PID_X_OUTPUT_LIMIT = 1 << 2;
+        const PID_V_TARGET_LIMIT = 1 << 3;
+        const PID_V_ERROR_SUM_LIMIT = 1 << 4;
+        const PID_V_OUTPUT_LIMIT = 1 << 5;
+        const PID_ID_TARGET_LIMIT = 1 << 6;
+        const PID_ID_ERROR_SUM_LIMIT = 1 << 7;
+        const PID_ID_OUTPUT_LIMIT = 1 << 8;
+        const PID_IQ_TARGET_LIMIT = 1 << 9;
+        const PID_IQ_ERROR_SUM_LIMIT = 1 << 10;
+        const PID_IQ_OUTPUT_LIMIT = 1 << 11;
+        const IPARK_CURLIM_LIMIT_U = 1 << 12;
+        const IPARK_CURLIM_LIMIT_V = 1 << 13;
+        const IPARK_CURLIM_LIMIT_W = 1 << 14;
+        const IPARK_CURLIM_LIMIT_SUM = 1 << 15;
+        const FLUX_DECODER_PHI_E_ERROR = 1 << 16;
+        const HALL_DECODER_PHI_E_ERROR = 1 << 17;
+        const AENC_DECODER_VALIDITY_ERROR = 1 << 18;
+        const ADC_I_CLIPPED = 1 << 24;
+        const AENC_CLIPPED = 1 << 25;
+        const ADC_VM_UNDERVOLTAGE = 1 << 28;
+        const ADC_VM_OVERVOLTAGE = 1 << 29;
+    }
+}
+
+/// Indirect-access index into `PID_ERROR_DATA` via `PID_ERROR_ADDR`.
+#[derive(Debug, Copy, Clone)]
+pub enum PidError {
+    FluxTarget = 0,
+    FluxActual = 1,
+    FluxError = 2,
+    FluxErrorSum = 3,
+    TorqueTarget = 4,
+    TorqueActual = 5,
+    TorqueError = 6,
+    TorqueErrorSum = 7,
+    VelocityTarget = 8,
+    VelocityActual = 9,
+    VelocityError = 10,
+    VelocityErrorSum = 11,
+    PositionTarget = 12,
+    PositionActual = 13,
+    PositionError = 14,
+    PositionErrorSum = 15,
+}
+
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
+    /// Reads and decodes `STATUS_FLAGS`.
+    pub fn poll_status(&mut self) -> Result<StatusFlags, Tmc4671Error> {
+        let bits = self.read_register(registers::STATUS_FLAGS)?;
+        Ok(StatusFlags::from_bits_truncate(bits))
+    }
+
+    /// Writes `STATUS_MASK`, enabling only the given flags to latch in `STATUS_FLAGS`.
+    pub fn set_status_mask(&mut self, mask: StatusFlags) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::STATUS_MASK, mask.bits())
+    }
+
+    /// Reads a single PID error term via the `PID_ERROR_ADDR`/`PID_ERROR_DATA`
+    /// indirect-access pair.
+    pub fn read_pid_error(&mut self, which: PidError) -> Result<u32, Tmc4671Error> {
+        self.write_register(registers::PID_ERROR_ADDR, which as u32)?;
+        self.read_register(registers::PID_ERROR_DATA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spi::Datagram;
+    use embedded_hal::spi::{ErrorType, Operation};
+    use nom::Finish;
+    use std::collections::HashMap;
+
+    /// Minimal register file that answers reads/writes like the real chip, so the
+    /// `STATUS_FLAGS` decoding and `PID_ERROR_ADDR`/`PID_ERROR_DATA` indirect access can
+    /// be tested without hardware.
+    struct FakeRegisters(HashMap<u8, u32>);
+
+    impl FakeRegisters {
+        fn new() -> Self {
+            FakeRegisters(HashMap::new())
+        }
+
+        fn with(mut self, address: u8, value: u32) -> Self {
+            self.0.insert(address, value);
+            self
+        }
+    }
+
+    impl ErrorType for FakeRegisters {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SpiDevice for FakeRegisters {
+        fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            for op in operations {
+                if let Operation::TransferInPlace(buf) = op {
+                    let (_, request) = Datagram::parse(buf).finish().unwrap();
+
+                    if request.write_not_read {
+                        self.0.insert(request.address, request.data);
+                    }
+
+                    let data = *self.0.get(&request.address).unwrap_or(&0);
+                    buf.copy_from_slice(
+                        &Datagram {
+                            write_not_read: request.write_not_read,
+                            address: request.address,
+                            data,
+                        }
+                        .bytes(),
+                    );
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn poll_status_decodes_overvoltage_and_pid_limit_flags() {
+        let regs = FakeRegisters::new().with(
+            registers::STATUS_FLAGS,
+            (StatusFlags::ADC_VM_OVERVOLTAGE | StatusFlags::PID_IQ_OUTPUT_LIMIT).bits(),
+        );
+        let mut tmc = Tmc4671::new(regs);
+
+        let status = tmc.poll_status().unwrap();
+
+        assert!(status.contains(StatusFlags::ADC_VM_OVERVOLTAGE));
+        assert!(status.contains(StatusFlags::PID_IQ_OUTPUT_LIMIT));
+        assert!(!status.contains(StatusFlags::PID_X_TARGET_LIMIT));
+    }
+
+    #[test]
+    fn set_status_mask_writes_status_mask_register() {
+        let mut tmc = Tmc4671::new(FakeRegisters::new());
+
+        tmc.set_status_mask(StatusFlags::ADC_VM_OVERVOLTAGE | StatusFlags::ADC_I_CLIPPED)
+            .unwrap();
+
+        assert_eq!(
+            tmc.read_register(registers::STATUS_MASK).unwrap(),
+            (StatusFlags::ADC_VM_OVERVOLTAGE | StatusFlags::ADC_I_CLIPPED).bits()
+        );
+    }
+
+    #[test]
+    fn read_pid_error_selects_the_requested_term_via_indirect_access() {
+        let regs = FakeRegisters::new().with(registers::PID_ERROR_DATA, 0x4242);
+        let mut tmc = Tmc4671::new(regs);
+
+        let value = tmc.read_pid_error(PidError::VelocityErrorSum).unwrap();
+
+        assert_eq!(value, 0x4242);
+        assert_eq!(
+            tmc.read_register(registers::PID_ERROR_ADDR).unwrap(),
+            PidError::VelocityErrorSum as u32
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Driver-managed enable/chip-select line.
+//!
+//! By default [`Tmc4671::new`](crate::Tmc4671::new) assumes the HAL's `SpiDevice`
+//! already manages chip select. [`Tmc4671::with_enable`](crate::Tmc4671::with_enable)
+//! instead has the driver itself assert/de-assert a GPIO enable line around every
+//! transaction, so protocol timing doesn't leak into user code.
+use embedded_hal::digital::OutputPin;
+
+/// Active polarity of an enable line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Polarity {
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// Asserted before, and de-asserted after, every SPI transaction.
+pub trait EnableLine {
+    fn assert(&mut self);
+    fn deassert(&mut self);
+}
+
+/// No-op [`EnableLine`] used when the `SpiDevice` already manages chip select.
+pub struct NoEnable;
+
+impl EnableLine for NoEnable {
+    fn assert(&mut self) {}
+    fn deassert(&mut self) {}
+}
+
+/// A GPIO [`OutputPin`] driven as the chip's enable line, with configurable polarity.
+pub struct EnablePin<P: OutputPin> {
+    pin: P,
+    polarity: Polarity,
+}
+
+impl<P: OutputPin> EnablePin<P> {
+    pub fn new(pin: P, polarity: Polarity) -> Self {
+        EnablePin { pin, polarity }
+    }
+}
+
+impl<P: OutputPin> EnableLine for EnablePin<P> {
+    fn assert(&mut self) {
+        let _ = match self.polarity {
+            Polarity::ActiveHigh => self.pin.set_high(),
+            Polarity::ActiveLow => self.pin.set_low(),
+        };
+    }
+
+    fn deassert(&mut self) {
+        let _ = match self.polarity {
+            Polarity::ActiveHigh => self.pin.set_low(),
+            Polarity::ActiveLow => self.pin.set_high(),
+        };
+    }
+}
@@ -0,0 +1,339 @@
+//! High-level field-oriented-control (FOC) setup and motion control, layered on top of
+//! the raw register access in [`Tmc4671`].
+//!
+//! Selector values taken from the TMC4671-LA datasheet.
+//! ©2022 TRINAMIC Motion Control GmbH & Co. KG, Hamburg, Germany
+use crate::enable::EnableLine;
+use crate::register::registers::{MotorType, MotorTypeNPolePairs, PidFluxPFluxI, PidTorqueFluxLimits};
+use crate::register::Register;
+use crate::spi::registers;
+use crate::{Tmc4671, Tmc4671Error};
+use embedded_hal::spi::SpiDevice;
+
+const PHI_E_SELECTION_OPEN_LOOP: u32 = 1;
+const PHI_E_SELECTION_ABN: u32 = 3;
+const PHI_E_SELECTION_HALL: u32 = 5;
+
+const VELOCITY_SELECTION_PHI_E_OPEN_LOOP: u32 = 1;
+const VELOCITY_SELECTION_PHI_E_ABN: u32 = 3;
+const VELOCITY_SELECTION_PHI_E_HALL: u32 = 5;
+
+const POSITION_SELECTION_PHI_E_OPEN_LOOP: u32 = 1;
+const POSITION_SELECTION_PHI_E_ABN: u32 = 3;
+const POSITION_SELECTION_PHI_E_HALL: u32 = 5;
+
+/// Motor electrical parameters, applied with [`Tmc4671::configure_motor`].
+#[derive(Debug, Copy, Clone)]
+pub struct MotorConfig {
+    pub motor_type: MotorType,
+    pub pole_pairs: u16,
+    pub pwm_maxcnt: u16,
+    pub adc_i0_scale_offset: u32,
+    pub adc_i1_scale_offset: u32,
+}
+
+impl MotorConfig {
+    /// Starts a config for `motor_type` with `pole_pairs`, at the datasheet's default
+    /// PWM period (`PWM_MAXCNT` = 0x0F9F) and no current-sense trim.
+    pub fn new(motor_type: MotorType, pole_pairs: u16) -> Self {
+        MotorConfig {
+            motor_type,
+            pole_pairs,
+            pwm_maxcnt: 0x0F9F,
+            adc_i0_scale_offset: 0x0000_8000,
+            adc_i1_scale_offset: 0x0000_8000,
+        }
+    }
+
+    /// Sets the `PWM_MAXCNT` period that derives the PWM frequency from the system clock.
+    pub fn pwm_maxcnt(mut self, pwm_maxcnt: u16) -> Self {
+        self.pwm_maxcnt = pwm_maxcnt;
+        self
+    }
+
+    /// Sets the `ADC_I0_SCALE_OFFSET`/`ADC_I1_SCALE_OFFSET` current-sense trim.
+    pub fn adc_scale_offset(mut self, i0_scale_offset: u32, i1_scale_offset: u32) -> Self {
+        self.adc_i0_scale_offset = i0_scale_offset;
+        self.adc_i1_scale_offset = i1_scale_offset;
+        self
+    }
+}
+
+/// Feedback source wired into `PHI_E_SELECTION`/`VELOCITY_SELECTION`/`POSITION_SELECTION`,
+/// applied with [`Tmc4671::set_commutation`].
+#[derive(Debug, Copy, Clone)]
+pub enum Commutation {
+    /// Estimated electrical angle, no position sensor.
+    OpenLoop,
+    /// ABN incremental encoder with `pulses_per_revolution` counts per mechanical turn.
+    AbnEncoder { pulses_per_revolution: u32 },
+    /// Digital Hall sensors, with the commutation angle at each of the three Hall edges.
+    Hall {
+        position_060_000: u16,
+        position_180_120: u16,
+        position_300_240: u16,
+    },
+}
+
+/// Ramp mode written to `MODE_RAMP_MODE_MOTION`, carrying the value(s) for the
+/// corresponding `*_TARGET` register that closes the control loop.
+#[derive(Debug, Copy, Clone)]
+pub enum MotionMode {
+    /// Writes `torque`/`flux` into the packed `PID_TORQUE_FLUX_TARGET` register, same
+    /// layout as [`Tmc4671::torque_flux_actual`]'s `(torque, flux)`.
+    Torque { torque: i16, flux: i16 },
+    /// Writes `PID_VELOCITY_TARGET`.
+    Velocity(i32),
+    /// Writes `PID_POSITION_TARGET`.
+    Position(i32),
+    /// Same packed target register as `Torque`. Does not change the commutation source;
+    /// that stays as set by [`Tmc4671::set_commutation`].
+    OpenLoop { torque: i16, flux: i16 },
+}
+
+impl MotionMode {
+    fn mode_bits(&self) -> u32 {
+        match self {
+            MotionMode::Torque { .. } => 1,
+            MotionMode::Velocity(_) => 2,
+            MotionMode::Position(_) => 3,
+            MotionMode::OpenLoop { .. } => 8,
+        }
+    }
+}
+
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
+    /// Applies motor type, pole pairs, PWM period and current-sense trim as one
+    /// batched transaction.
+    pub fn configure_motor(&mut self, config: MotorConfig) -> Result<(), Tmc4671Error> {
+        let motor_type_n_pole_pairs = MotorTypeNPolePairs {
+            motor_type: config.motor_type,
+            n_pole_pairs: config.pole_pairs,
+        };
+
+        self.write_many(&[
+            (
+                MotorTypeNPolePairs::ADDRESS,
+                motor_type_n_pole_pairs.into_bits(),
+            ),
+            (registers::PWM_MAXCNT, config.pwm_maxcnt as u32),
+            (registers::ADC_I0_SCALE_OFFSET, config.adc_i0_scale_offset),
+            (registers::ADC_I1_SCALE_OFFSET, config.adc_i1_scale_offset),
+        ])
+    }
+
+    /// Wires `PHI_E_SELECTION`/`VELOCITY_SELECTION`/`POSITION_SELECTION` (and the
+    /// feedback-specific setup registers) for the chosen commutation source.
+    pub fn set_commutation(&mut self, commutation: Commutation) -> Result<(), Tmc4671Error> {
+        match commutation {
+            Commutation::OpenLoop => self.write_many(&[
+                (registers::PHI_E_SELECTION, PHI_E_SELECTION_OPEN_LOOP),
+                (
+                    registers::VELOCITY_SELECTION,
+                    VELOCITY_SELECTION_PHI_E_OPEN_LOOP,
+                ),
+                (
+                    registers::POSITION_SELECTION,
+                    POSITION_SELECTION_PHI_E_OPEN_LOOP,
+                ),
+            ]),
+            Commutation::AbnEncoder {
+                pulses_per_revolution,
+            } => self.write_many(&[
+                (registers::ABN_DECODER_PPR, pulses_per_revolution),
+                (registers::PHI_E_SELECTION, PHI_E_SELECTION_ABN),
+                (registers::VELOCITY_SELECTION, VELOCITY_SELECTION_PHI_E_ABN),
+                (registers::POSITION_SELECTION, POSITION_SELECTION_PHI_E_ABN),
+            ]),
+            Commutation::Hall {
+                position_060_000,
+                position_180_120,
+                position_300_240,
+            } => self.write_many(&[
+                (registers::HALL_POSITION_060_000, position_060_000 as u32),
+                (registers::HALL_POSITION_180_120, position_180_120 as u32),
+                (registers::HALL_POSITION_300_240, position_300_240 as u32),
+                (registers::PHI_E_SELECTION, PHI_E_SELECTION_HALL),
+                (registers::VELOCITY_SELECTION, VELOCITY_SELECTION_PHI_E_HALL),
+                (registers::POSITION_SELECTION, POSITION_SELECTION_PHI_E_HALL),
+            ]),
+        }
+    }
+
+    /// Sets the flux PI controller gains (`PID_FLUX_P_FLUX_I`).
+    pub fn set_flux_pid(&mut self, p: u16, i: u16) -> Result<(), Tmc4671Error> {
+        self.write(PidFluxPFluxI {
+            pid_flux_p: p,
+            pid_flux_i: i,
+        })
+    }
+
+    /// Sets the torque PI controller gains (`PID_TORQUE_P_TORQUE_I`).
+    pub fn set_torque_pid(&mut self, p: u16, i: u16) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::PID_TORQUE_P_TORQUE_I, pi_bits(p, i))
+    }
+
+    /// Sets the velocity PI controller gains (`PID_VELOCITY_P_VELOCITY_I`).
+    pub fn set_velocity_pid(&mut self, p: u16, i: u16) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::PID_VELOCITY_P_VELOCITY_I, pi_bits(p, i))
+    }
+
+    /// Sets the position PI controller gains (`PID_POSITION_P_POSITION_I`).
+    pub fn set_position_pid(&mut self, p: u16, i: u16) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::PID_POSITION_P_POSITION_I, pi_bits(p, i))
+    }
+
+    /// Sets the torque and flux PI output limits (`PID_TORQUE_FLUX_LIMITS`).
+    pub fn set_torque_flux_limit(
+        &mut self,
+        torque_limit: u16,
+        flux_limit: u16,
+    ) -> Result<(), Tmc4671Error> {
+        self.write(PidTorqueFluxLimits {
+            torque_limit,
+            flux_limit,
+        })
+    }
+
+    /// Sets the velocity PI output limit (`PID_VELOCITY_LIMIT`).
+    pub fn set_velocity_limit(&mut self, limit: u32) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::PID_VELOCITY_LIMIT, limit)
+    }
+
+    /// Sets the position PI output range (`PID_POSITION_LIMIT_LOW`/`_HIGH`).
+    pub fn set_position_limits(&mut self, low: i32, high: i32) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::PID_POSITION_LIMIT_LOW, low as u32)?;
+        self.write_register(registers::PID_POSITION_LIMIT_HIGH, high as u32)
+    }
+
+    /// Programs `MODE_RAMP_MODE_MOTION` and writes `mode`'s payload to the `*_TARGET`
+    /// register the selected mode closes its loop on.
+    pub fn set_motion_mode(&mut self, mode: MotionMode) -> Result<(), Tmc4671Error> {
+        self.write_register(registers::MODE_RAMP_MODE_MOTION, mode.mode_bits())?;
+
+        match mode {
+            MotionMode::Torque { torque, flux } | MotionMode::OpenLoop { torque, flux } => self
+                .write_register(
+                    registers::PID_TORQUE_FLUX_TARGET,
+                    (flux as u16 as u32) | ((torque as u16 as u32) << 16),
+                ),
+            MotionMode::Velocity(target) => {
+                self.write_register(registers::PID_VELOCITY_TARGET, target as u32)
+            }
+            MotionMode::Position(target) => {
+                self.write_register(registers::PID_POSITION_TARGET, target as u32)
+            }
+        }
+    }
+
+    /// Reads the actual torque/flux currents (`PID_TORQUE_FLUX_ACTUAL`) as `(torque, flux)`.
+    pub fn torque_flux_actual(&mut self) -> Result<(i16, i16), Tmc4671Error> {
+        let bits = self.read_register(registers::PID_TORQUE_FLUX_ACTUAL)?;
+        Ok(((bits >> 16) as i16, (bits & 0xFFFF) as i16))
+    }
+
+    /// Reads the actual velocity (`PID_VELOCITY_ACTUAL`).
+    pub fn velocity_actual(&mut self) -> Result<i32, Tmc4671Error> {
+        Ok(self.read_register(registers::PID_VELOCITY_ACTUAL)? as i32)
+    }
+
+    /// Reads the actual position (`PID_POSITION_ACTUAL`).
+    pub fn position_actual(&mut self) -> Result<i32, Tmc4671Error> {
+        Ok(self.read_register(registers::PID_POSITION_ACTUAL)? as i32)
+    }
+}
+
+fn pi_bits(p: u16, i: u16) -> u32 {
+    ((p as u32) << 16) | i as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spi::Datagram;
+    use embedded_hal::spi::{ErrorType, Operation};
+    use nom::Finish;
+    use std::collections::HashMap;
+
+    /// Minimal register file that answers reads/writes like the real chip, so the
+    /// wire-level bit layout of packed registers can be checked without hardware.
+    struct FakeRegisters(HashMap<u8, u32>);
+
+    impl FakeRegisters {
+        fn new() -> Self {
+            FakeRegisters(HashMap::new())
+        }
+    }
+
+    impl ErrorType for FakeRegisters {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SpiDevice for FakeRegisters {
+        fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            for op in operations {
+                if let Operation::TransferInPlace(buf) = op {
+                    let (_, request) = Datagram::parse(buf).finish().unwrap();
+
+                    if request.write_not_read {
+                        self.0.insert(request.address, request.data);
+                    }
+
+                    let data = *self.0.get(&request.address).unwrap_or(&0);
+                    buf.copy_from_slice(
+                        &Datagram {
+                            write_not_read: request.write_not_read,
+                            address: request.address,
+                            data,
+                        }
+                        .bytes(),
+                    );
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn set_torque_pid_packs_p_into_the_high_half_and_i_into_the_low_half() {
+        let mut tmc = Tmc4671::new(FakeRegisters::new());
+
+        tmc.set_torque_pid(0x1234, 0x5678).unwrap();
+
+        assert_eq!(
+            tmc.read_register(registers::PID_TORQUE_P_TORQUE_I).unwrap(),
+            0x1234_5678
+        );
+    }
+
+    #[test]
+    fn set_motion_mode_torque_packs_torque_into_the_high_half_and_flux_into_the_low_half() {
+        let mut tmc = Tmc4671::new(FakeRegisters::new());
+
+        tmc.set_motion_mode(MotionMode::Torque {
+            torque: 0x1234,
+            flux: 0x5678,
+        })
+        .unwrap();
+
+        assert_eq!(
+            tmc.read_register(registers::PID_TORQUE_FLUX_TARGET)
+                .unwrap(),
+            0x1234_5678
+        );
+    }
+
+    #[test]
+    fn torque_flux_actual_decodes_torque_from_the_high_half_and_flux_from_the_low_half() {
+        let regs = FakeRegisters::new();
+        let mut tmc = Tmc4671::new(regs);
+        tmc.write_register(registers::PID_TORQUE_FLUX_ACTUAL, 0x1234_5678)
+            .unwrap();
+
+        assert_eq!(tmc.torque_flux_actual().unwrap(), (0x1234, 0x5678));
+    }
+}
@@ -1,20 +1,34 @@
 //! Crate for commanding the TMC4671 FOC IC over SPI
+use crate::enable::{EnableLine, NoEnable};
 use crate::spi::Datagram;
 use embedded_hal::spi::{Error, SpiDevice};
 use nom::Finish;
 use thiserror::Error;
 
-pub struct Tmc4671<SPI: SpiDevice> {
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod diagnostics;
+pub mod enable;
+pub mod foc;
+pub mod register;
+
+pub struct Tmc4671<SPI: SpiDevice, EN: EnableLine = NoEnable> {
     spi_device: SPI,
+    enable: EN,
 }
 
-impl<SPI: SpiDevice> Tmc4671<SPI> {
+impl<SPI: SpiDevice> Tmc4671<SPI, NoEnable> {
+    /// Creates a driver that does not manage the chip's enable/CS line itself, for
+    /// setups where the HAL's `SpiDevice` already manages chip select.
     pub fn new(spi_device: SPI) -> Self {
-        Tmc4671 { spi_device }
+        Tmc4671 {
+            spi_device,
+            enable: NoEnable,
+        }
     }
 }
 
-impl<SPI: SpiDevice> Tmc4671<SPI> {
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
     pub fn get_chip_info(
         &mut self,
         info: spi::constants::CHIP_INFO_ADDRESS,
@@ -24,7 +38,7 @@ impl<SPI: SpiDevice> Tmc4671<SPI> {
     }
 }
 
-impl<SPI: SpiDevice> Tmc4671<SPI> {
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
     pub fn read_register(&mut self, register: u8) -> Result<u32, Tmc4671Error> {
         let datagram = Datagram {
             write_not_read: false,
@@ -40,9 +54,10 @@ impl<SPI: SpiDevice> Tmc4671<SPI> {
     fn transfer_datagram(&mut self, datagram: Datagram) -> Result<Datagram, Tmc4671Error> {
         let mut buffer = datagram.bytes();
 
-        self.spi_device
-            .transfer_in_place(&mut buffer)
-            .map_err(|err| Tmc4671Error::CommunicationError(err.kind()))?;
+        self.enable.assert();
+        let result = self.spi_device.transfer_in_place(&mut buffer);
+        self.enable.deassert();
+        result.map_err(|err| Tmc4671Error::CommunicationError(err.kind()))?;
 
         let (_, received_datagram) = Datagram::parse(&buffer)
             .finish()
@@ -65,12 +80,142 @@ impl<SPI: SpiDevice> Tmc4671<SPI> {
     }
 }
 
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
+    /// Reads a register and decodes it into its typed field representation.
+    pub fn read<R: register::Register>(&mut self) -> Result<R, Tmc4671Error> {
+        let bits = self.read_register(R::ADDRESS)?;
+        Ok(R::from_bits(bits))
+    }
+
+    /// Encodes a typed register value and writes it back to the chip.
+    pub fn write<R: register::Register>(&mut self, register: R) -> Result<(), Tmc4671Error> {
+        self.write_register(R::ADDRESS, register.into_bits())
+    }
+}
+
+/// Maximum number of datagrams a single [`Tmc4671::transfer_batch`]/[`Tmc4671::write_many`]
+/// call can carry, bounding the wire buffer so it can live on the stack.
+pub const MAX_BATCH_LEN: usize = 32;
+
+impl<SPI: SpiDevice, P: embedded_hal::digital::OutputPin> Tmc4671<SPI, enable::EnablePin<P>> {
+    /// Creates a driver that asserts `enable_pin` before each transaction and de-asserts
+    /// it after, so `read_register`/`write_register` manage the line transparently.
+    /// `enable_pin` is active-high; use [`Tmc4671::with_enable_polarity`] for active-low.
+    pub fn with_enable(spi_device: SPI, enable_pin: P) -> Self {
+        Self::with_enable_polarity(spi_device, enable_pin, enable::Polarity::ActiveHigh)
+    }
+
+    /// Like [`Tmc4671::with_enable`], with an explicit active polarity for `enable_pin`.
+    pub fn with_enable_polarity(
+        spi_device: SPI,
+        enable_pin: P,
+        polarity: enable::Polarity,
+    ) -> Self {
+        Tmc4671 {
+            spi_device,
+            enable: enable::EnablePin::new(enable_pin, polarity),
+        }
+    }
+}
+
+impl<SPI: SpiDevice, EN: EnableLine> Tmc4671<SPI, EN> {
+    /// Serializes `datagrams` into one contiguous buffer and performs a single
+    /// `transfer_in_place`, instead of one SPI transaction per datagram.
+    pub fn transfer_batch(&mut self, datagrams: &[Datagram]) -> Result<Vec<Datagram>, Tmc4671Error> {
+        let mut responses = vec![
+            Datagram {
+                write_not_read: false,
+                address: 0,
+                data: 0,
+            };
+            datagrams.len()
+        ];
+
+        self.transfer_batch_into(datagrams, &mut responses)?;
+
+        Ok(responses)
+    }
+
+    /// `no_std`-friendly slice-in/slice-out variant of
+    /// [`transfer_batch`](Self::transfer_batch): `responses` must be the same length as
+    /// `datagrams`, and both are bounded by [`MAX_BATCH_LEN`] so the wire buffer can live
+    /// on the stack.
+    pub fn transfer_batch_into(
+        &mut self,
+        datagrams: &[Datagram],
+        responses: &mut [Datagram],
+    ) -> Result<(), Tmc4671Error> {
+        if datagrams.len() != responses.len() {
+            return Err(Tmc4671Error::BatchLengthMismatch);
+        }
+
+        if datagrams.len() > MAX_BATCH_LEN {
+            return Err(Tmc4671Error::BatchTooLarge);
+        }
+
+        let mut buffer = [0u8; MAX_BATCH_LEN * 5];
+        let buffer = &mut buffer[..datagrams.len() * 5];
+
+        for (chunk, datagram) in buffer.chunks_exact_mut(5).zip(datagrams) {
+            chunk.copy_from_slice(&datagram.bytes());
+        }
+
+        self.enable.assert();
+        let result = self.spi_device.transfer_in_place(buffer);
+        self.enable.deassert();
+        result.map_err(|err| Tmc4671Error::CommunicationError(err.kind()))?;
+
+        for (chunk, response) in buffer.chunks_exact(5).zip(responses.iter_mut()) {
+            let (_, received_datagram) = Datagram::parse(chunk)
+                .finish()
+                .map_err(|_err| Tmc4671Error::ParseError)?;
+
+            *response = received_datagram;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `registers` (address, value pairs) as a single batched transaction, so an
+    /// init sequence of dozens of writes runs as one transfer instead of one per register.
+    pub fn write_many(&mut self, registers: &[(u8, u32)]) -> Result<(), Tmc4671Error> {
+        if registers.len() > MAX_BATCH_LEN {
+            return Err(Tmc4671Error::BatchTooLarge);
+        }
+
+        let blank = Datagram {
+            write_not_read: true,
+            address: 0,
+            data: 0,
+        };
+        let mut datagrams = [blank; MAX_BATCH_LEN];
+        let mut responses = [blank; MAX_BATCH_LEN];
+
+        for (slot, (address, data)) in datagrams.iter_mut().zip(registers) {
+            *slot = Datagram {
+                write_not_read: true,
+                address: *address,
+                data: *data,
+            };
+        }
+
+        self.transfer_batch_into(
+            &datagrams[..registers.len()],
+            &mut responses[..registers.len()],
+        )
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Tmc4671Error {
     #[error("failed to parse data")]
     ParseError,
     #[error("SPI communication failed")]
     CommunicationError(embedded_hal::spi::ErrorKind),
+    #[error("batch of more than {MAX_BATCH_LEN} datagrams")]
+    BatchTooLarge,
+    #[error("datagrams and responses slices had different lengths")]
+    BatchLengthMismatch,
 }
 
 pub mod spi {
@@ -199,7 +344,8 @@ pub mod spi {
         pub const PID_TORQUE_FLUX_LIMITS: u8 = 0x5E;
         pub const PID_VELOCITY_LIMIT: u8 = 0x60;
         pub const PID_POSITION_LIMIT_LOW: u8 = 0x61;
-        pub const PID_POSITION_LIMIT_HIGH: u8 = 0x61;
+        // Not 0x61 — that would duplicate PID_POSITION_LIMIT_LOW above.
+        pub const PID_POSITION_LIMIT_HIGH: u8 = 0x62;
         pub const MODE_RAMP_MODE_MOTION: u8 = 0x63;
         pub const PID_TORQUE_FLUX_TARGET: u8 = 0x64;
         pub const PID_TORQUE_FLUX_OFFSET: u8 = 0x65;
@@ -242,7 +388,98 @@ pub mod spi {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use embedded_hal::spi::{ErrorType, Operation};
+
+    /// Parses every `TransferInPlace` buffer as a [`Datagram`] and re-serializes it back
+    /// into the same buffer, so a round trip through [`Tmc4671::transfer_batch_into`]
+    /// actually exercises the wire format instead of just echoing request bytes untouched.
+    struct LoopbackSpi;
+
+    impl ErrorType for LoopbackSpi {
+        type Error = core::convert::Infallible;
+    }
+
+    impl SpiDevice for LoopbackSpi {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+            for op in operations {
+                let Operation::TransferInPlace(buf) = op else {
+                    panic!("LoopbackSpi only supports TransferInPlace operations");
+                };
+
+                for chunk in buf.chunks_exact_mut(5) {
+                    let (_, datagram) = Datagram::parse(chunk)
+                        .finish()
+                        .expect("loopback buffer should contain a valid datagram");
+                    chunk.copy_from_slice(&datagram.bytes());
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    fn blank_datagram() -> Datagram {
+        Datagram {
+            write_not_read: false,
+            address: 0,
+            data: 0,
+        }
+    }
 
     #[test]
-    fn it_works() {}
+    fn transfer_batch_into_serializes_and_parses_each_datagram() {
+        let mut tmc = Tmc4671::new(LoopbackSpi);
+        let datagrams = [
+            Datagram {
+                write_not_read: true,
+                address: 0x01,
+                data: 0xDEAD_BEEF,
+            },
+            Datagram {
+                write_not_read: false,
+                address: 0x7C,
+                data: 0x0000_0001,
+            },
+        ];
+        let mut responses = [blank_datagram(); 2];
+
+        tmc.transfer_batch_into(&datagrams, &mut responses).unwrap();
+
+        for (response, datagram) in responses.iter().zip(&datagrams) {
+            assert_eq!(response.write_not_read, datagram.write_not_read);
+            assert_eq!(response.address, datagram.address);
+            assert_eq!(response.data, datagram.data);
+        }
+    }
+
+    #[test]
+    fn transfer_batch_into_rejects_a_batch_over_the_limit() {
+        let mut tmc = Tmc4671::new(LoopbackSpi);
+        let datagrams = vec![
+            Datagram {
+                write_not_read: true,
+                address: 0,
+                data: 0
+            };
+            MAX_BATCH_LEN + 1
+        ];
+        let mut responses = vec![blank_datagram(); MAX_BATCH_LEN + 1];
+
+        assert!(matches!(
+            tmc.transfer_batch_into(&datagrams, &mut responses),
+            Err(Tmc4671Error::BatchTooLarge)
+        ));
+    }
+
+    #[test]
+    fn transfer_batch_into_rejects_mismatched_lengths() {
+        let mut tmc = Tmc4671::new(LoopbackSpi);
+        let datagrams = [blank_datagram(); 2];
+        let mut responses = [blank_datagram(); 1];
+
+        assert!(matches!(
+            tmc.transfer_batch_into(&datagrams, &mut responses),
+            Err(Tmc4671Error::BatchLengthMismatch)
+        ));
+    }
 }
@@ -0,0 +1,184 @@
+//! Typed register field abstraction.
+//!
+//! Several TMC4671 registers pack multiple fields into one 32-bit word. Rather than
+//! hand-assembling/shifting bits around [`Tmc4671::read_register`](crate::Tmc4671::read_register)/
+//! [`write_register`](crate::Tmc4671::write_register), a type implementing [`Register`] can be
+//! read/written directly via [`Tmc4671::read`](crate::Tmc4671::read)/[`write`](crate::Tmc4671::write).
+
+use crate::spi::registers as addr;
+
+/// A register whose 32-bit value can be decoded into (and re-encoded from) typed fields.
+pub trait Register: Sized + Copy {
+    /// Register address, see [`crate::spi::registers`].
+    const ADDRESS: u8;
+
+    fn from_bits(bits: u32) -> Self;
+    fn into_bits(self) -> u32;
+}
+
+/// A value that can be packed into (and unpacked from) a bit range of a [`Register`].
+pub trait RegisterField: Copy {
+    fn from_bits(bits: u32) -> Self;
+    fn into_bits(self) -> u32;
+}
+
+macro_rules! impl_register_field_for_uint {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl RegisterField for $ty {
+                fn from_bits(bits: u32) -> Self {
+                    bits as $ty
+                }
+
+                fn into_bits(self) -> u32 {
+                    self as u32
+                }
+            }
+        )*
+    };
+}
+
+impl_register_field_for_uint!(u8, u16, u32);
+
+/// Declares a [`Register`] impl for a struct whose fields are packed bit ranges of a
+/// single 32-bit register.
+macro_rules! register {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident at $address:path {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty { offset: $offset:expr, mask: $mask:expr }
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $crate::register::Register for $name {
+            const ADDRESS: u8 = $address;
+
+            fn from_bits(bits: u32) -> Self {
+                Self {
+                    $(
+                        $field: <$ty as $crate::register::RegisterField>::from_bits((bits >> $offset) & $mask),
+                    )*
+                }
+            }
+
+            fn into_bits(self) -> u32 {
+                0
+                $(
+                    | ($crate::register::RegisterField::into_bits(self.$field) & $mask) << $offset
+                )*
+            }
+        }
+    };
+}
+
+/// Generated register field layouts, see [`register!`].
+pub mod registers {
+    use super::*;
+
+    /// `MOTOR_TYPE` field of [`MotorTypeNPolePairs`].
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum MotorType {
+        NoMotor = 0,
+        /// Single phase DC motor.
+        SinglePhaseDc = 1,
+        Stepper = 2,
+        /// Three phase BLDC/PMSM motor.
+        ThreePhaseBldc = 3,
+    }
+
+    impl RegisterField for MotorType {
+        fn from_bits(bits: u32) -> Self {
+            match bits {
+                0 => MotorType::NoMotor,
+                1 => MotorType::SinglePhaseDc,
+                2 => MotorType::Stepper,
+                _ => MotorType::ThreePhaseBldc,
+            }
+        }
+
+        fn into_bits(self) -> u32 {
+            self as u32
+        }
+    }
+
+    register! {
+        /// Motor type and pole-pair count, see `MOTOR_TYPE_N_POLE_PAIRS`.
+        pub struct MotorTypeNPolePairs at addr::MOTOR_TYPE_N_POLE_PAIRS {
+            pub n_pole_pairs: u16 { offset: 0, mask: 0xFFFF },
+            pub motor_type: MotorType { offset: 24, mask: 0x3 },
+        }
+    }
+
+    register! {
+        /// Break-before-make delay for the high/low side gate drivers, see `PWM_BBM_H_BBM_L`.
+        pub struct PwmBbmHBbmL at addr::PWM_BBM_H_BBM_L {
+            pub bbm_l: u8 { offset: 0, mask: 0xFF },
+            pub bbm_h: u8 { offset: 8, mask: 0xFF },
+        }
+    }
+
+    register! {
+        /// Flux PI controller gains, see `PID_FLUX_P_FLUX_I`.
+        pub struct PidFluxPFluxI at addr::PID_FLUX_P_FLUX_I {
+            pub pid_flux_i: u16 { offset: 0, mask: 0xFFFF },
+            pub pid_flux_p: u16 { offset: 16, mask: 0xFFFF },
+        }
+    }
+
+    register! {
+        /// Torque and flux PI output limits, see `PID_TORQUE_FLUX_LIMITS`.
+        pub struct PidTorqueFluxLimits at addr::PID_TORQUE_FLUX_LIMITS {
+            pub flux_limit: u16 { offset: 0, mask: 0xFFFF },
+            pub torque_limit: u16 { offset: 16, mask: 0xFFFF },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::registers::{MotorType, MotorTypeNPolePairs, PidFluxPFluxI, PidTorqueFluxLimits};
+    use super::Register;
+
+    #[test]
+    fn pid_flux_p_flux_i_round_trips() {
+        let reg = PidFluxPFluxI {
+            pid_flux_p: 0x1234,
+            pid_flux_i: 0x5678,
+        };
+
+        assert_eq!(reg.into_bits(), 0x1234_5678);
+        assert_eq!(PidFluxPFluxI::from_bits(reg.into_bits()), reg);
+    }
+
+    #[test]
+    fn pid_torque_flux_limits_keeps_both_fields() {
+        let reg = PidTorqueFluxLimits {
+            torque_limit: 0x0BB8,
+            flux_limit: 0x0FA0,
+        };
+
+        assert_eq!(reg.into_bits(), 0x0BB8_0FA0);
+        assert_eq!(PidTorqueFluxLimits::from_bits(reg.into_bits()), reg);
+    }
+
+    #[test]
+    fn motor_type_n_pole_pairs_round_trips() {
+        let reg = MotorTypeNPolePairs {
+            motor_type: MotorType::ThreePhaseBldc,
+            n_pole_pairs: 7,
+        };
+
+        assert_eq!(MotorTypeNPolePairs::from_bits(reg.into_bits()), reg);
+    }
+}